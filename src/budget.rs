@@ -0,0 +1,121 @@
+// --------------------------------------------------------------------------------
+// |
+// |    **Bounded Dedup Strategy**
+// |
+// |  `seen_lines_hashes` normally grows for the lifetime of a `DataCleanser`,
+// |  which is fine for corpora that fit in memory but not for streams too
+// |  large to track exactly. `DedupStrategy::Bounded` caps the resident set
+// |  at a fixed capacity, evicting the oldest hash once full (FIFO), trading
+// |  a small false-negative rate — an evicted hash can reappear and be
+// |  treated as new — for a hard memory ceiling.
+// |
+// --------------------------------------------------------------------------------
+use fnv::FnvHashSet;
+use std::collections::VecDeque;
+
+pub struct BoundedHashSet {
+    capacity: usize,
+    set: FnvHashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl BoundedHashSet {
+    fn new(capacity: usize) -> Self {
+        BoundedHashSet {
+            capacity,
+            set: FnvHashSet::default(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Mirrors `HashSet::insert`: returns `true` if `hash` was not already
+    /// present. Evicts the oldest tracked hash first if at capacity.
+    ///
+    /// `capacity == 0` means "track nothing": every hash is treated as new
+    /// (nothing is ever deduped against), and nothing is ever stored. This
+    /// is the smallest possible memory ceiling, not "no ceiling" -- without
+    /// this early return the capacity check below would evict and then
+    /// still insert, so the set would always hold one hash, more than a
+    /// capacity of zero allows.
+    fn insert(&mut self, hash: u64) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.set.contains(&hash) {
+            return false;
+        }
+        if self.set.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        self.set.insert(hash);
+        self.order.push_back(hash);
+        true
+    }
+}
+
+/// Exact dedup by default; switches to `Bounded` when a `max_tracked_hashes`
+/// budget is configured.
+pub enum DedupStrategy {
+    Exact(FnvHashSet<u64>),
+    Bounded(BoundedHashSet),
+}
+
+impl DedupStrategy {
+    pub fn new(max_tracked_hashes: Option<usize>) -> Self {
+        match max_tracked_hashes {
+            Some(capacity) => DedupStrategy::Bounded(BoundedHashSet::new(capacity)),
+            None => DedupStrategy::Exact(FnvHashSet::default()),
+        }
+    }
+
+    pub fn insert(&mut self, hash: u64) -> bool {
+        match self {
+            DedupStrategy::Exact(set) => set.insert(hash),
+            DedupStrategy::Bounded(bounded) => bounded.insert(hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_capacity_never_remembers_and_never_dedups() {
+        let mut set = BoundedHashSet::new(0);
+        assert!(set.insert(1));
+        // Same hash inserted again is still reported as new, since
+        // capacity 0 never actually stores anything to dedup against.
+        assert!(set.insert(1));
+        assert!(set.set.is_empty());
+    }
+
+    #[test]
+    fn duplicate_hash_is_rejected_within_capacity() {
+        let mut set = BoundedHashSet::new(2);
+        assert!(set.insert(1));
+        assert!(!set.insert(1));
+    }
+
+    #[test]
+    fn oldest_hash_is_evicted_fifo_once_full() {
+        let mut set = BoundedHashSet::new(2);
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(set.insert(3)); // full at {1, 2}; evicts 1, now tracks {2, 3}
+        // 1 was evicted, so re-inserting it is new again -- and doing so
+        // evicts 2 (the new oldest) in turn, leaving {1, 3} tracked.
+        assert!(set.insert(1));
+        assert!(!set.set.contains(&2));
+        assert!(set.set.contains(&1));
+        assert!(set.set.contains(&3));
+    }
+
+    #[test]
+    fn dedup_strategy_new_selects_bounded_only_when_configured() {
+        assert!(matches!(DedupStrategy::new(None), DedupStrategy::Exact(_)));
+        assert!(matches!(DedupStrategy::new(Some(10)), DedupStrategy::Bounded(_)));
+    }
+}
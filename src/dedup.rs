@@ -0,0 +1,227 @@
+// --------------------------------------------------------------------------------
+// |
+// |    **Near-Duplicate Detection via MinHash + LSH**
+// |
+// |  Exact dedup (via `seen_lines_hashes` in `lib.rs`) only catches
+// |  byte-identical lines after normalization. Scraped LM corpora are
+// |  dominated by near-duplicates instead: the same sentence with a word
+// |  swapped, a timestamp updated, or whitespace reflowed. This module
+// |  estimates Jaccard similarity between shingled lines with MinHash, and
+// |  uses banded LSH so a new line is only compared against lines that are
+// |  likely to be similar rather than every previously seen line.
+// |
+// --------------------------------------------------------------------------------
+use fnv::{FnvHashMap, FnvHasher};
+use std::hash::Hasher;
+
+/// Largest prime below 2^64, used as the MinHash modulus.
+const LARGE_PRIME: u64 = 0xFFFF_FFFF_FFFF_FFC5;
+
+/// MinHash signature generation plus a banded LSH index, used to flag lines
+/// that are near-duplicates of an already-accepted line.
+pub struct NearDupIndex {
+    num_perm: usize,
+    shingle_size: usize,
+    threshold: f64,
+    rows_per_band: usize,
+    num_bands: usize,
+    coeffs: Vec<(u64, u64)>,
+    bands: Vec<FnvHashMap<u64, Vec<usize>>>,
+    signatures: Vec<Vec<u64>>,
+}
+
+impl NearDupIndex {
+    /// Validates `num_perm`/`shingle_size`/`threshold` before building the
+    /// index: `num_perm` and `shingle_size` feed a division and a
+    /// `windows()` call respectively, both of which panic on zero, and these
+    /// three values are exposed straight from Python, so bad input needs to
+    /// surface as a `PyValueError` rather than an unhandled panic.
+    pub fn new(num_perm: usize, shingle_size: usize, threshold: f64) -> Result<Self, String> {
+        if num_perm == 0 {
+            return Err("num_perm must be greater than 0".to_string());
+        }
+        if shingle_size == 0 {
+            return Err("shingle_size must be greater than 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("threshold must be between 0.0 and 1.0".to_string());
+        }
+
+        let rows_per_band = Self::pick_rows_per_band(num_perm, threshold);
+        let num_bands = num_perm / rows_per_band;
+
+        // Fixed coefficients keep signatures reproducible across runs; a
+        // simple LCG is enough to spread them out, no need for a `rand`
+        // dependency just for this.
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let coeffs = (0..num_perm)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let a = (seed >> 1) | 1; // keep `a` odd and non-zero
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (a, seed)
+            })
+            .collect();
+
+        Ok(NearDupIndex {
+            num_perm,
+            shingle_size,
+            threshold,
+            rows_per_band,
+            num_bands,
+            coeffs,
+            bands: vec![FnvHashMap::default(); num_bands],
+            signatures: Vec::new(),
+        })
+    }
+
+    /// Picks rows-per-band so the LSH collision curve's inflection point
+    /// lands close to `threshold`, using the standard `(1/b)^(1/r) ~= threshold`
+    /// approximation. Only divisors of `num_perm` are valid since `b * r`
+    /// must equal `num_perm`.
+    fn pick_rows_per_band(num_perm: usize, threshold: f64) -> usize {
+        (1..=num_perm)
+            .filter(|r| num_perm.is_multiple_of(*r))
+            .min_by(|&r1, &r2| {
+                let score = |r: usize| {
+                    let b = (num_perm / r) as f64;
+                    ((1.0 / b).powf(1.0 / r as f64) - threshold).abs()
+                };
+                score(r1).partial_cmp(&score(r2)).unwrap()
+            })
+            .unwrap_or(num_perm)
+    }
+
+    fn hash_shingle(s: &str) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hasher.write(s.as_bytes());
+        hasher.finish()
+    }
+
+    /// Shingles `text` into overlapping k-token windows, falling back to
+    /// k-character windows for lines too short to yield a token shingle.
+    fn shingles(&self, text: &str) -> Vec<u64> {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        if tokens.len() >= self.shingle_size {
+            return tokens
+                .windows(self.shingle_size)
+                .map(|w| Self::hash_shingle(&w.join(" ")))
+                .collect();
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        if chars.len() < self.shingle_size {
+            vec![Self::hash_shingle(text)]
+        } else {
+            chars
+                .windows(self.shingle_size)
+                .map(|w| Self::hash_shingle(&w.iter().collect::<String>()))
+                .collect()
+        }
+    }
+
+    fn signature(&self, text: &str) -> Vec<u64> {
+        let shingles = self.shingles(text);
+        self.coeffs
+            .iter()
+            .map(|&(a, b)| {
+                shingles
+                    .iter()
+                    .map(|&h| a.wrapping_mul(h).wrapping_add(b) % LARGE_PRIME)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn band_key(signature: &[u64]) -> u64 {
+        let mut hasher = FnvHasher::default();
+        for v in signature {
+            hasher.write_u64(*v);
+        }
+        hasher.finish()
+    }
+
+    /// Builds a fresh, empty index with the same configuration as `self`.
+    /// Used to give each parallel worker in `process_files` its own local
+    /// index before the results are merged back sequentially. `self`'s
+    /// parameters were already validated by `new`, so this can't fail.
+    pub fn fresh(&self) -> Self {
+        Self::new(self.num_perm, self.shingle_size, self.threshold).expect("`self` was already validated by `new`")
+    }
+
+    /// Checks `text` against the index and, if it is not a near-duplicate of
+    /// anything already indexed, inserts it under `line_id`.
+    ///
+    /// Returns `true` when the line should be kept (no indexed line that
+    /// shares an LSH band bucket with it reaches `threshold` estimated
+    /// Jaccard similarity), `false` when it should be dropped as a
+    /// near-duplicate, in which case it is not indexed.
+    pub fn insert(&mut self, text: &str, line_id: usize) -> bool {
+        let sig = self.signature(text);
+
+        for band in 0..self.num_bands {
+            let start = band * self.rows_per_band;
+            let end = start + self.rows_per_band;
+            let key = Self::band_key(&sig[start..end]);
+
+            if let Some(candidates) = self.bands[band].get(&key) {
+                for &candidate_id in candidates {
+                    let candidate_sig = &self.signatures[candidate_id];
+                    let equal = sig.iter().zip(candidate_sig.iter()).filter(|(a, b)| a == b).count();
+                    let estimated_jaccard = equal as f64 / self.num_perm as f64;
+                    if estimated_jaccard >= self.threshold {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        for band in 0..self.num_bands {
+            let start = band * self.rows_per_band;
+            let end = start + self.rows_per_band;
+            let key = Self::band_key(&sig[start..end]);
+            self.bands[band].entry(key).or_default().push(line_id);
+        }
+        self.signatures.push(sig);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_num_perm_is_rejected() {
+        assert!(NearDupIndex::new(0, 5, 0.8).is_err());
+    }
+
+    #[test]
+    fn zero_shingle_size_is_rejected() {
+        assert!(NearDupIndex::new(128, 0, 0.8).is_err());
+    }
+
+    #[test]
+    fn threshold_out_of_range_is_rejected() {
+        assert!(NearDupIndex::new(128, 5, 1.5).is_err());
+        assert!(NearDupIndex::new(128, 5, -0.1).is_err());
+    }
+
+    #[test]
+    fn near_duplicate_line_is_rejected_above_threshold() {
+        // shingle_size=1 makes these word-level (unigram) shingles: the two
+        // sentences differ by a single word ("jumps" vs "leaps"), giving a
+        // true Jaccard of 7/9 ~= 0.78 -- comfortably above threshold=0.7.
+        let mut index = NearDupIndex::new(128, 1, 0.7).unwrap();
+        assert!(index.insert("the quick brown fox jumps over the lazy dog", 0));
+        assert!(!index.insert("the quick brown fox leaps over the lazy dog", 1));
+    }
+
+    #[test]
+    fn dissimilar_line_is_kept() {
+        let mut index = NearDupIndex::new(128, 1, 0.7).unwrap();
+        assert!(index.insert("the quick brown fox jumps over the lazy dog", 0));
+        assert!(index.insert("completely unrelated sentence about something else entirely", 1));
+    }
+}
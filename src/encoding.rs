@@ -0,0 +1,91 @@
+// --------------------------------------------------------------------------------
+// |
+// |    **Decode Policy**
+// |
+// |  Real-world crawl dumps mix UTF-8 with Latin-1, CP1252, and outright
+// |  binary junk. A single invalid byte used to make `process_file` fail
+// |  the whole call via `reader.lines()`. This module decides, line by
+// |  line, what to do with bytes that aren't valid UTF-8 instead of
+// |  aborting the entire file.
+// |
+// --------------------------------------------------------------------------------
+use encoding_rs::Encoding;
+
+/// How to turn a line's raw bytes into a `String`.
+pub enum DecodePolicy {
+    /// Require valid UTF-8; lines that aren't are skipped rather than
+    /// aborting the whole file. This is the default, matching the previous
+    /// behavior for well-formed input while no longer being fatal for
+    /// occasional bad lines.
+    Strict,
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of skipping.
+    Lossy,
+    /// Decode using a named source encoding (e.g. `"windows-1252"`,
+    /// `"latin1"`), for files known to come from a non-UTF-8 source.
+    Named(&'static Encoding),
+}
+
+impl DecodePolicy {
+    /// Returns a plain `Result` (not `PyResult`) so this module -- and
+    /// anything that only needs to construct a `DecodePolicy`, like
+    /// `Cleanser::new` -- never has to link PyO3/CPython FFI symbols into a
+    /// non-Python binary such as the `cargo test` executable. Callers
+    /// exposed to Python convert the `Err` string to a `PyValueError` at
+    /// that boundary.
+    pub fn parse(name: Option<&str>) -> Result<Self, String> {
+        match name {
+            None | Some("strict") => Ok(DecodePolicy::Strict),
+            Some("lossy") => Ok(DecodePolicy::Lossy),
+            Some(other) => Encoding::for_label(other.as_bytes())
+                .map(DecodePolicy::Named)
+                .ok_or_else(|| format!("unknown encoding: {other}")),
+        }
+    }
+
+    /// Decodes a line's raw bytes per the policy. `None` means the line
+    /// should be skipped, which only happens under `Strict`.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            DecodePolicy::Strict => std::str::from_utf8(bytes).ok().map(str::to_owned),
+            DecodePolicy::Lossy => Some(String::from_utf8_lossy(bytes).into_owned()),
+            DecodePolicy::Named(encoding) => Some(encoding.decode(bytes).0.into_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_rejects_invalid_utf8() {
+        assert_eq!(DecodePolicy::Strict.decode(&[0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn lossy_replaces_invalid_utf8() {
+        let decoded = DecodePolicy::Lossy.decode(&[0xff, 0xfe]).unwrap();
+        assert!(decoded.contains('\u{fffd}'));
+    }
+
+    #[test]
+    fn named_encoding_transcodes_latin1() {
+        // 0xe9 is "é" in latin1/windows-1252, not valid UTF-8 on its own.
+        let policy = DecodePolicy::parse(Some("latin1")).unwrap();
+        assert_eq!(policy.decode(&[0xe9]).unwrap(), "é");
+    }
+
+    #[test]
+    fn unknown_encoding_name_is_rejected() {
+        assert!(DecodePolicy::parse(Some("not-a-real-encoding")).is_err());
+    }
+
+    #[test]
+    fn trim_on_decoded_text_strips_nbsp() {
+        // NBSP (U+00A0) is whitespace to `str::trim` but not to ASCII-only
+        // byte trimming, so this only passes if trimming happens after
+        // decoding, on the `str`, not before on raw bytes.
+        let decoded = DecodePolicy::Lossy.decode("\u{a0}hello\u{a0}".as_bytes()).unwrap();
+        assert_eq!(decoded.trim(), "hello");
+    }
+}
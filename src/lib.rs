@@ -8,113 +8,546 @@
 // |  - `fnv::FnvHashSet`: A fast hash set for storing hashes of seen lines.
 // |  - `aho_corasick::AhoCorasick`: For efficient multi-pattern string matching.
 // |  - `std::collections::hash_map::DefaultHasher`, `std::hash::{Hash, Hasher}`: For hashing lines.
+// |  - `tokenizer`: Optional word segmentation for whitespace-free scripts (CJK).
+// |  - `dedup`: MinHash + LSH near-duplicate detection.
+// |  - `encoding`: Decode policy for non-UTF-8 and mixed-encoding input.
+// |  - `rayon`: Thread pool for parallel multi-file processing.
+// |  - `budget`: Memory-bounded dedup strategy for streaming mode.
 // |
 // --------------------------------------------------------------------------------
 use pyo3::prelude::*;
 use std::fs::File;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, BufWriter, Write};
 use std::path::Path;
 use fnv::FnvHashSet;
 use aho_corasick::AhoCorasick;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use rayon::prelude::*;
+
+mod tokenizer;
+use tokenizer::{contains_toxic_phrase, Segmenter};
+mod dedup;
+use dedup::NearDupIndex;
+mod encoding;
+use encoding::DecodePolicy;
+mod budget;
+use budget::DedupStrategy;
 
 // --------------------------------------------------------------------------------
 // |
-// |    **DataCleanser Struct**
+// |    **Cleanser**
 // |
-// |  This struct is the core of the data cleaning functionality. It holds the
-// |  configuration for the cleaning process and the state of the cleaned data.
+// |  Holds the configuration and state for the cleaning process, and all of
+// |  the actual cleaning logic. Deliberately a plain struct, not a
+// |  `#[pyclass]`: under PyO3's `extension-module` feature (needed for the
+// |  Python-loadable build), these types can only be constructed inside a
+// |  process that embeds Python, which a standalone `cargo test` binary
+// |  doesn't. Keeping the logic here, independent of PyO3, lets `mod tests`
+// |  below construct and drive it directly. `DataCleanser` is the thin
+// |  `#[pyclass]` wrapper that exposes it to Python.
 // |
 // --------------------------------------------------------------------------------
-#[pyclass(name = "DataCleanser")]
-struct DataCleanser {
+struct Cleanser {
     min_length: usize,
     toxic_keywords_automaton: AhoCorasick,
-    seen_lines_hashes: FnvHashSet<u64>,
+    toxic_keyword_token_seqs: Vec<Vec<String>>,
+    segmenter: Segmenter,
+    decode_policy: DecodePolicy,
+    seen_lines_hashes: DedupStrategy,
+    near_dup_index: Option<NearDupIndex>,
     cleaned_lines: Vec<String>,
+    output_writer: Option<BufWriter<File>>,
+    line_count: usize,
 }
 
-// --------------------------------------------------------------------------------
-// |
-// |    **Python Methods for DataCleanser**
-// |
-// |  These methods are exposed to Python and provide the interface for the
-// |  data cleaning functionality.
-// |
-// --------------------------------------------------------------------------------
-#[pymethods]
-impl DataCleanser {
+impl Cleanser {
     // --------------------------------------------------------------------------------
-    // |  `new` - The constructor for the DataCleanser class.
+    // |  `new` - Builds a `Cleanser` from the constructor arguments Python sees.
+    // |
+    // |  `tokenize` switches `min_length` from counting Unicode scalar values
+    // |  to counting words, and switches toxic-keyword matching from a raw
+    // |  substring scan to whole-token comparison. This is needed for CJK
+    // |  text, which has no whitespace word boundaries for the default path
+    // |  to rely on. `language` is a hint passed to the segmenter; it is
+    // |  optional because the bundled segmenter works reasonably well
+    // |  without it.
+    // |
+    // |  `similarity_threshold` turns on fuzzy dedup via MinHash + LSH
+    // |  (see `dedup::NearDupIndex`) in addition to the exact-hash dedup
+    // |  below; leaving it unset keeps the original exact-dedup-only
+    // |  behavior. `num_perm` and `shingle_size` tune the MinHash signature
+    // |  and are ignored unless `similarity_threshold` is set.
+    // |
+    // |  `decode_policy` controls what happens when a line isn't valid
+    // |  UTF-8: `"strict"` (default) skips the line, `"lossy"` replaces bad
+    // |  sequences with U+FFFD, and any other value is looked up as a named
+    // |  source encoding (e.g. `"windows-1252"`) to transcode from.
+    // |
+    // |  `output_path` turns on streaming mode: accepted lines are written
+    // |  to a buffered writer instead of being accumulated in
+    // |  `cleaned_lines`. Each file's accepted lines are still collected in
+    // |  memory by `process_file_local` and written once that file has been
+    // |  read, so peak memory follows the largest single file's cleaned
+    // |  output, not the whole corpus. `max_tracked_hashes` bounds
+    // |  `seen_lines_hashes` to a fixed capacity (FIFO eviction, see
+    // |  `budget::BoundedHashSet`) for streams too large to dedup exactly;
+    // |  leaving it unset tracks every hash seen. The file-local hash set
+    // |  that `process_file_local` uses is not bounded by it.
+    // |
+    // |  Returns a plain `Result` (see the `Cleanser` doc comment above) --
+    // |  `DataCleanser::new` converts the `Err` string to a `PyValueError`.
     // --------------------------------------------------------------------------------
-    #[new]
-    #[pyo3(signature = (min_length=20, toxic_keywords=None))]
-    fn new(min_length: usize, toxic_keywords: Option<Vec<String>>) -> PyResult<Self> {
-        let patterns = toxic_keywords.unwrap_or_else(Vec::new);
-        let automaton = AhoCorasick::new(patterns).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        min_length: usize,
+        toxic_keywords: Option<Vec<String>>,
+        tokenize: bool,
+        language: Option<String>,
+        similarity_threshold: Option<f64>,
+        num_perm: usize,
+        shingle_size: usize,
+        decode_policy: Option<String>,
+        output_path: Option<String>,
+        max_tracked_hashes: Option<usize>,
+    ) -> Result<Self, String> {
+        let patterns = toxic_keywords.unwrap_or_default();
+        let automaton = AhoCorasick::new(&patterns).map_err(|e| e.to_string())?;
+        let segmenter = Segmenter::new(tokenize, language.as_deref());
+        // Tokenized keyword matching needs to compare whole token sequences
+        // (not just single tokens) against each keyword, so keywords are
+        // segmented the same way the input lines are.
+        let toxic_keyword_token_seqs = patterns
+            .iter()
+            .map(|k| segmenter.tokenize(&k.to_lowercase()).into_iter().map(str::to_string).collect())
+            .collect();
+        let near_dup_index = similarity_threshold.map(|threshold| NearDupIndex::new(num_perm, shingle_size, threshold)).transpose()?;
+        let output_writer = output_path
+            .map(|path| -> Result<_, String> {
+                let file = File::create(&path).map_err(|e| e.to_string())?;
+                Ok(BufWriter::new(file))
+            })
+            .transpose()?;
 
-        Ok(DataCleanser {
+        Ok(Cleanser {
             min_length,
             toxic_keywords_automaton: automaton,
-            seen_lines_hashes: FnvHashSet::default(),
+            toxic_keyword_token_seqs,
+            segmenter,
+            decode_policy: DecodePolicy::parse(decode_policy.as_deref())?,
+            seen_lines_hashes: DedupStrategy::new(max_tracked_hashes),
+            near_dup_index,
             cleaned_lines: Vec::new(),
+            output_writer,
+            line_count: 0,
         })
     }
 
     // --------------------------------------------------------------------------------
-    // |  `process_file` - Processes a file, cleaning and deduplicating the lines.
+    // |  `process_file_local` - Reads and filters one file without touching
+    // |  shared state, so it can run concurrently across files in `process_files`.
+    // |
+    // |  Reads raw bytes and splits on `b'\n'` instead of `reader.lines()`,
+    // |  so a single invalid byte sequence no longer fails the whole call;
+    // |  the decode policy decides per-line whether to skip, replace, or
+    // |  transcode it. Outside tokenized mode, the `AhoCorasick` automaton
+    // |  runs once on the raw bytes before decoding, so a toxic line is
+    // |  matched even if it fails to decode under `Strict`, and again on the decoded,
+    // |  lowercased text, which catches case-insensitive matches decode
+    // |  normalizes into view. Dedup here is file-local only (its own
+    // |  `FnvHashSet` and, if configured, a fresh `NearDupIndex`); global
+    // |  uniqueness across files is enforced afterwards by `merge_local_lines`.
     // --------------------------------------------------------------------------------
-    fn process_file(&mut self, filepath: &str) -> PyResult<usize> {
+    fn process_file_local(&self, filepath: &str) -> io::Result<Vec<(String, String)>> {
         let path = Path::new(filepath);
         let file = File::open(path)?;
-        let reader = io::BufReader::new(file);
+        let mut reader = io::BufReader::new(file);
+        let mut raw_line: Vec<u8> = Vec::new();
+        let mut local_hashes: FnvHashSet<u64> = FnvHashSet::default();
+        let mut local_near_dup = self.near_dup_index.as_ref().map(NearDupIndex::fresh);
+        let mut local_lines: Vec<(String, String)> = Vec::new();
 
-        for line in reader.lines() {
-            let line = line?;
-            let main_content = line.split('#').next().unwrap_or("").trim();
-            let cleaned_content = main_content.replace(' ', " ");
+        loop {
+            raw_line.clear();
+            if reader.read_until(b'\n', &mut raw_line)? == 0 {
+                break;
+            }
+
+            let mut line_bytes: &[u8] = &raw_line;
+            if line_bytes.last() == Some(&b'\n') {
+                line_bytes = &line_bytes[..line_bytes.len() - 1];
+            }
+            if line_bytes.last() == Some(&b'\r') {
+                line_bytes = &line_bytes[..line_bytes.len() - 1];
+            }
 
-            if cleaned_content.chars().count() < self.min_length {
+            let main_content_bytes = match line_bytes.iter().position(|&b| b == b'#') {
+                Some(idx) => &line_bytes[..idx],
+                None => line_bytes,
+            };
+
+            // Match on the raw bytes before decoding, so keyword filtering
+            // does not depend on whether the line decodes (under `Strict`, a
+            // line that fails to decode is skipped below either way).
+            // ASCII-lowercased first so this stays case-insensitive like the
+            // post-decode check below -- this is the only keyword check that
+            // ever runs for a line that fails to decode, so it has to carry
+            // that guarantee on its own. Skipped in tokenized mode, where
+            // keywords match whole tokens only, never substrings.
+            let lowercased_bytes = main_content_bytes.to_ascii_lowercase();
+            if !self.segmenter.is_tokenized()
+                && self.toxic_keywords_automaton.patterns_len() > 0
+                && self.toxic_keywords_automaton.is_match(&lowercased_bytes)
+            {
                 continue;
             }
 
+            let decoded = match self.decode_policy.decode(main_content_bytes) {
+                Some(text) => text,
+                None => continue,
+            };
+
+            // `.trim()` runs on the decoded `str`, not raw bytes, so it
+            // strips Unicode whitespace -- including U+00A0 (NBSP), which
+            // scraped HTML commonly leaves behind as `&nbsp;` -- rather
+            // than only ASCII whitespace.
+            let trimmed = decoded.trim();
+            // Normalizes any remaining interior NBSP to a literal space.
+            let cleaned_content = trimmed.replace('\u{a0}', " ");
             let lowercased = cleaned_content.to_lowercase();
-            if self.toxic_keywords_automaton.patterns_len() > 0 && self.toxic_keywords_automaton.is_match(&lowercased) {
+
+            if self.segmenter.is_tokenized() {
+                let tokens = self.segmenter.tokenize(&lowercased);
+                if tokens.len() < self.min_length {
+                    continue;
+                }
+                if contains_toxic_phrase(&tokens, &self.toxic_keyword_token_seqs) {
+                    continue;
+                }
+            } else {
+                if cleaned_content.chars().count() < self.min_length {
+                    continue;
+                }
+                if self.toxic_keywords_automaton.patterns_len() > 0 && self.toxic_keywords_automaton.is_match(&lowercased) {
+                    continue;
+                }
+            }
+
+            let mut hasher = DefaultHasher::new();
+            lowercased.hash(&mut hasher);
+            let line_hash = hasher.finish();
+
+            if !local_hashes.insert(line_hash) {
                 continue;
             }
 
+            if let Some(index) = local_near_dup.as_mut() {
+                if !index.insert(&lowercased, local_lines.len()) {
+                    continue;
+                }
+            }
+
+            local_lines.push((cleaned_content, lowercased));
+        }
+        Ok(local_lines)
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `merge_local_lines` - Folds one file's locally-deduplicated lines into
+    // |  the shared `seen_lines_hashes`/`near_dup_index` state, then either
+    // |  streams each surviving line straight to `output_writer` or appends
+    // |  it to `cleaned_lines`, depending on whether streaming mode is on.
+    // |  This is the only place global state is mutated, so it's also the
+    // |  one place that needs no locking even when called after a
+    // |  multi-threaded `process_files` pass.
+    // --------------------------------------------------------------------------------
+    fn merge_local_lines(&mut self, local_lines: Vec<(String, String)>) -> io::Result<()> {
+        for (cleaned_content, lowercased) in local_lines {
             let mut hasher = DefaultHasher::new();
             lowercased.hash(&mut hasher);
             let line_hash = hasher.finish();
 
-            if self.seen_lines_hashes.insert(line_hash) {
-                self.cleaned_lines.push(cleaned_content);
+            if !self.seen_lines_hashes.insert(line_hash) {
+                continue;
+            }
+
+            if let Some(index) = self.near_dup_index.as_mut() {
+                if !index.insert(&lowercased, self.line_count) {
+                    continue;
+                }
             }
+
+            match self.output_writer.as_mut() {
+                Some(writer) => writeln!(writer, "{}", cleaned_content)?,
+                None => self.cleaned_lines.push(cleaned_content),
+            }
+            self.line_count += 1;
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `flush_output` - Flushes `output_writer`, propagating any `io::Error`
+    // |  instead of letting `Drop` swallow it silently.
+    // --------------------------------------------------------------------------------
+    fn flush_output(&mut self) -> io::Result<()> {
+        if let Some(writer) = self.output_writer.as_mut() {
+            writer.flush()?;
         }
-        Ok(self.cleaned_lines.len())
+        Ok(())
     }
 
     // --------------------------------------------------------------------------------
     // |  `save_cleaned_to_file` - Saves the cleaned lines to a file.
+    // |
+    // |  Only meaningful outside streaming mode: when `new` was given an
+    // |  `output_path`, lines are written during processing and
+    // |  `cleaned_lines` stays empty, so this writes nothing.
     // --------------------------------------------------------------------------------
-    fn save_cleaned_to_file(&self, output_path: &str) -> PyResult<()> {
+    fn save_cleaned_to_file(&self, output_path: &str) -> io::Result<()> {
         let path = Path::new(output_path);
-        let mut file = File::create(path).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        let mut file = File::create(path)?;
 
         for line in &self.cleaned_lines {
-            writeln!(file, "{}", line).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+            writeln!(file, "{}", line)?;
         }
 
         Ok(())
     }
+}
+
+// --------------------------------------------------------------------------------
+// |
+// |    **DataCleanser Struct**
+// |
+// |  A thin `#[pyclass]` wrapper around `Cleanser` (see its doc comment):
+// |  every method here just converts arguments/errors at the Python
+// |  boundary and delegates the actual work to `self.inner`.
+// |
+// --------------------------------------------------------------------------------
+#[pyclass(name = "DataCleanser")]
+struct DataCleanser {
+    inner: Cleanser,
+}
+
+// --------------------------------------------------------------------------------
+// |
+// |    **Python Methods for DataCleanser**
+// |
+// |  These methods are exposed to Python and provide the interface for the
+// |  data cleaning functionality.
+// |
+// --------------------------------------------------------------------------------
+#[pymethods]
+impl DataCleanser {
+    #[new]
+    #[pyo3(signature = (min_length=20, toxic_keywords=None, tokenize=false, language=None, similarity_threshold=None, num_perm=128, shingle_size=5, decode_policy=None, output_path=None, max_tracked_hashes=None))]
+    // Every argument here is a distinct, independently optional knob exposed
+    // to Python; grouping them into a config struct would just move the
+    // same ten fields one level down without making any of them less
+    // independent, so this is accepted rather than worked around.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        min_length: usize,
+        toxic_keywords: Option<Vec<String>>,
+        tokenize: bool,
+        language: Option<String>,
+        similarity_threshold: Option<f64>,
+        num_perm: usize,
+        shingle_size: usize,
+        decode_policy: Option<String>,
+        output_path: Option<String>,
+        max_tracked_hashes: Option<usize>,
+    ) -> PyResult<Self> {
+        let inner = Cleanser::new(
+            min_length,
+            toxic_keywords,
+            tokenize,
+            language,
+            similarity_threshold,
+            num_perm,
+            shingle_size,
+            decode_policy,
+            output_path,
+            max_tracked_hashes,
+        )
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e))?;
+
+        Ok(DataCleanser { inner })
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `process_file` - Processes a file, cleaning and deduplicating the lines.
+    // |
+    // |  The filtering work runs under `py.allow_threads`, releasing the GIL
+    // |  for the duration so other Python threads keep running while a large
+    // |  file is cleaned.
+    // --------------------------------------------------------------------------------
+    fn process_file(&mut self, py: Python<'_>, filepath: &str) -> PyResult<usize> {
+        let local_lines = py.allow_threads(|| self.inner.process_file_local(filepath))?;
+        self.inner.merge_local_lines(local_lines)?;
+        self.inner.flush_output()?;
+        Ok(self.inner.line_count)
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `process_files` - Processes many files in parallel on a rayon thread pool.
+    // |
+    // |  Each file is filtered and locally deduplicated independently (its own
+    // |  `FnvHashSet`/`NearDupIndex`, per `process_file_local`), then the
+    // |  per-file results are merged into `self` sequentially, in `paths`
+    // |  order, so the final `seen_lines_hashes`/`cleaned_lines` stay globally
+    // |  unique and the result is deterministic regardless of which worker
+    // |  finishes first. Runs under `py.allow_threads` like `process_file`.
+    // --------------------------------------------------------------------------------
+    #[pyo3(signature = (paths, num_threads=None))]
+    fn process_files(&mut self, py: Python<'_>, paths: Vec<String>, num_threads: Option<usize>) -> PyResult<usize> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads.unwrap_or(0))
+            .build()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+        let results: Vec<io::Result<Vec<(String, String)>>> = py.allow_threads(|| {
+            pool.install(|| paths.par_iter().map(|p| self.inner.process_file_local(p)).collect())
+        });
+
+        for result in results {
+            self.inner.merge_local_lines(result?)?;
+        }
+        self.inner.flush_output()?;
+
+        Ok(self.inner.line_count)
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `save_cleaned_to_file` - Saves the cleaned lines to a file.
+    // |
+    // |  Only meaningful outside streaming mode: when `new` was given an
+    // |  `output_path`, lines are written during processing and
+    // |  `cleaned_lines` stays empty, so this writes nothing.
+    // --------------------------------------------------------------------------------
+    fn save_cleaned_to_file(&self, output_path: &str) -> PyResult<()> {
+        self.inner.save_cleaned_to_file(output_path)?;
+        Ok(())
+    }
 
     // --------------------------------------------------------------------------------
-    // |  `count` - Returns the number of unique lines found.
+    // |  `count` - Returns the number of unique lines found so far. Tracked
+    // |  as a running counter rather than `cleaned_lines.len()` since
+    // |  streaming mode never populates `cleaned_lines`.
     // --------------------------------------------------------------------------------
     #[getter]
     fn count(&self) -> PyResult<usize> {
-        Ok(self.cleaned_lines.len())
+        Ok(self.inner.line_count)
+    }
+
+    // --------------------------------------------------------------------------------
+    // |  `close` - Flushes the streaming output writer, if any.
+    // |
+    // |  `process_file`/`process_files` already flush after every call, so
+    // |  this is only needed if the caller wants to force a flush (or
+    // |  surface a flush error) without processing another file. `Drop`'s
+    // |  implicit flush on `BufWriter` discards I/O errors silently, so this
+    // |  is the only way a write failure (e.g. disk full) reaches Python as
+    // |  an exception instead of just losing the last buffered lines. A
+    // |  no-op outside streaming mode, and safe to call more than once.
+    // --------------------------------------------------------------------------------
+    fn close(&mut self) -> PyResult<()> {
+        self.inner.flush_output()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a bare `Cleanser` (not the `#[pyclass] DataCleanser` wrapper),
+    /// so these tests exercise the cleaning logic without ever touching
+    /// PyO3 -- see the `Cleanser` doc comment for why that matters for
+    /// `cargo test`.
+    fn test_cleanser(toxic_keywords: Vec<String>, decode_policy: DecodePolicy) -> Cleanser {
+        let automaton = AhoCorasick::new(&toxic_keywords).unwrap();
+        Cleanser {
+            min_length: 1,
+            toxic_keywords_automaton: automaton,
+            toxic_keyword_token_seqs: Vec::new(),
+            segmenter: Segmenter::new(false, None),
+            decode_policy,
+            seen_lines_hashes: DedupStrategy::new(None),
+            near_dup_index: None,
+            cleaned_lines: Vec::new(),
+            output_writer: None,
+            line_count: 0,
+        }
+    }
+
+    fn write_temp_file(name: &str, bytes: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("data_cleanser_test_{}_{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn nbsp_is_trimmed_after_decoding() {
+        let cleanser = test_cleanser(Vec::new(), DecodePolicy::Lossy);
+        let path = write_temp_file("nbsp", "\u{a0}hello world\u{a0}\n".as_bytes());
+        let lines = cleanser.process_file_local(&path).unwrap();
+        assert_eq!(lines, vec![("hello world".to_string(), "hello world".to_string())]);
+    }
+
+    #[test]
+    fn toxic_keyword_is_filtered_even_when_line_fails_to_decode() {
+        let cleanser = test_cleanser(vec!["bad".to_string()], DecodePolicy::Strict);
+        // Invalid UTF-8 (0xff) containing the raw toxic byte pattern; under
+        // `Strict` this never reaches `decode_policy.decode` successfully,
+        // so only the pre-decode byte-level automaton check can catch it.
+        let mut bytes = b"bad".to_vec();
+        bytes.push(0xff);
+        bytes.push(b'\n');
+        let path = write_temp_file("undecodable_toxic", &bytes);
+        let lines = cleanser.process_file_local(&path).unwrap();
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn undecodable_toxic_line_is_filtered_regardless_of_case() {
+        let cleanser = test_cleanser(vec!["bad".to_string()], DecodePolicy::Strict);
+        // Same as `toxic_keyword_is_filtered_even_when_line_fails_to_decode`
+        // but the raw bytes are upper-case while the stored pattern is
+        // lower-case; the pre-decode byte check has to lowercase its
+        // haystack itself since decode (which would normally do this) never
+        // succeeds for this line.
+        let mut bytes = b"BAD".to_vec();
+        bytes.push(0xff);
+        bytes.push(b'\n');
+        let path = write_temp_file("undecodable_toxic_upper", &bytes);
+        let lines = cleanser.process_file_local(&path).unwrap();
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn clean_undecodable_line_is_skipped_not_kept() {
+        let cleanser = test_cleanser(Vec::new(), DecodePolicy::Strict);
+        let path = write_temp_file("undecodable_clean", &[b'h', b'i', 0xff, b'\n']);
+        let lines = cleanser.process_file_local(&path).unwrap();
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn flush_output_surfaces_io_errors_instead_of_swallowing_them() {
+        let mut cleanser = test_cleanser(Vec::new(), DecodePolicy::Lossy);
+        let out_path = write_temp_file("flush_target", b"");
+        let file = File::create(&out_path).unwrap();
+        cleanser.output_writer = Some(BufWriter::new(file));
+
+        cleanser.merge_local_lines(vec![("hello world".to_string(), "hello world".to_string())]).unwrap();
+        assert!(cleanser.flush_output().is_ok());
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "hello world\n");
+
+        // Calling flush again once there's nothing new to flush must stay a
+        // no-op Ok, same as `close()` (which just calls this) would.
+        assert!(cleanser.flush_output().is_ok());
     }
 }
 
@@ -0,0 +1,119 @@
+// --------------------------------------------------------------------------------
+// |
+// |    **Tokenizer Module**
+// |
+// |  Provides word segmentation for scripts that don't use whitespace to
+// |  separate words (Chinese, Japanese). Backed by `jieba-rs`, which is
+// |  tuned for Chinese but degrades gracefully on mixed CJK/Latin text.
+// |
+// --------------------------------------------------------------------------------
+use jieba_rs::Jieba;
+
+/// Word segmentation mode selected on `DataCleanser::new`.
+///
+/// `Whitespace` is the default and matches the historical behavior of
+/// splitting on Unicode scalar values / ASCII whitespace. `Jieba` runs a
+/// dictionary-based segmenter tuned for Chinese, so `min_length` counts
+/// words instead of characters and keyword matching respects word
+/// boundaries. `CharWise` is used for `language="ja"`: jieba's dictionary
+/// is Chinese-specific and would mis-segment Japanese, so rather than
+/// silently running the wrong segmenter, each non-whitespace Unicode
+/// scalar is treated as its own token.
+pub enum Segmenter {
+    Whitespace,
+    Jieba(Jieba),
+    CharWise,
+}
+
+impl Segmenter {
+    pub fn new(tokenize: bool, language: Option<&str>) -> Self {
+        if !tokenize {
+            return Segmenter::Whitespace;
+        }
+        match language {
+            Some(lang) if lang.eq_ignore_ascii_case("ja") => Segmenter::CharWise,
+            _ => Segmenter::Jieba(Jieba::new()),
+        }
+    }
+
+    /// Splits `text` into non-whitespace tokens according to the selected
+    /// mode. Whitespace-only tokens (e.g. the spaces jieba's `cut` leaves
+    /// in place between segments) are filtered out so they don't inflate
+    /// `min_length` word counts or dilute keyword-phrase matching.
+    pub fn tokenize<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        match self {
+            Segmenter::Whitespace => text.split_whitespace().collect(),
+            Segmenter::Jieba(jieba) => jieba
+                .cut(text, false)
+                .into_iter()
+                .filter(|t| !t.trim().is_empty())
+                .collect(),
+            Segmenter::CharWise => text
+                .split_whitespace()
+                .flat_map(|word| word.char_indices().map(move |(i, c)| &word[i..i + c.len_utf8()]))
+                .collect(),
+        }
+    }
+
+    pub fn is_tokenized(&self) -> bool {
+        !matches!(self, Segmenter::Whitespace)
+    }
+}
+
+/// Returns `true` if any keyword's token sequence occurs as a contiguous
+/// sliding window within `tokens`. Single-token keywords are a window of
+/// length 1, so this subsumes the old single-token `HashSet` lookup while
+/// also catching multi-token phrases (e.g. `"bad word"`, or a CJK compound
+/// the segmenter splits into more than one token) that a per-token set
+/// membership check can never match.
+pub fn contains_toxic_phrase(tokens: &[&str], keyword_token_seqs: &[Vec<String>]) -> bool {
+    keyword_token_seqs.iter().any(|seq| {
+        !seq.is_empty()
+            && seq.len() <= tokens.len()
+            && tokens
+                .windows(seq.len())
+                .any(|window| window.iter().zip(seq.iter()).all(|(t, k)| *t == k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jieba_tokenize_drops_whitespace_tokens() {
+        let segmenter = Segmenter::new(true, None);
+        let tokens = segmenter.tokenize("this is a bad word example");
+        assert!(tokens.iter().all(|t| !t.trim().is_empty()));
+    }
+
+    #[test]
+    fn multi_token_phrase_is_matched() {
+        let segmenter = Segmenter::new(true, None);
+        let tokens = segmenter.tokenize("this is a bad word example");
+        let keyword_seqs = vec![vec!["bad".to_string(), "word".to_string()]];
+        assert!(contains_toxic_phrase(&tokens, &keyword_seqs));
+    }
+
+    #[test]
+    fn single_token_match_still_works() {
+        let tokens = vec!["this", "is", "toxic", "text"];
+        let keyword_seqs = vec![vec!["toxic".to_string()]];
+        assert!(contains_toxic_phrase(&tokens, &keyword_seqs));
+    }
+
+    #[test]
+    fn no_match_when_phrase_absent() {
+        let tokens = vec!["this", "is", "clean", "text"];
+        let keyword_seqs = vec![vec!["bad".to_string(), "word".to_string()]];
+        assert!(!contains_toxic_phrase(&tokens, &keyword_seqs));
+    }
+
+    #[test]
+    fn japanese_hint_uses_charwise_segmentation_not_jieba() {
+        let segmenter = Segmenter::new(true, Some("ja"));
+        assert!(matches!(segmenter, Segmenter::CharWise));
+        let tokens = segmenter.tokenize("\u{3053}\u{3093}\u{306b}\u{3061}\u{306f}");
+        assert_eq!(tokens.len(), 5);
+    }
+}